@@ -1,13 +1,26 @@
+use std::collections::HashMap;
+use std::env;
 use std::fs::{self, File};
 use std::path::PathBuf;
-use std::io::{self, prelude::*, BufRead, Write,BufReader};
+use std::io::{self, prelude::*, Write};
+
+use crate::settings;
+
+// Environment variable moving the config file and word lists into a single directory, which is
+// also what the --data-dir flag sets
+pub const DATA_DIR_VAR: &str = "TERM_TYPIST_HOME";
 
 pub fn create_config() -> std::io::Result<()> {
-    let config_dir = dirs::config_dir().expect("Unable to determine config directory");
-    let folder_path = config_dir.join("term-typist");
+    let folder_path = match data_dir() {
+        Some(dir) => dir,
+        None => {
+            let config_dir = dirs::config_dir().expect("Unable to determine config directory");
+            config_dir.join("term-typist")
+        }
+    };
 
     if !folder_exists(&folder_path) {
-        fs::create_dir(&folder_path)?;
+        fs::create_dir_all(&folder_path)?;
     }
 
     let file_path = folder_path.join("term-typist.conf");
@@ -19,8 +32,84 @@ pub fn create_config() -> std::io::Result<()> {
     Ok(())
 }
 
-// Function to write number of words according to parameter into the config file
-pub fn write_nb_of_words(nb_cmds: i32) -> io::Result<()> {
+// Values of every setting, read once from the config file with the defaults from settings.rs
+// filling in the keys that aren't set
+pub struct Config {
+    values: HashMap<&'static str, String>,
+}
+
+impl Config {
+    // Function to get the value of a setting as text. Every setting has a value since the defaults
+    // fill in the missing ones, so an unknown key is a typo in the code asking for it.
+    pub fn text(&self, key: &str) -> &str {
+        self.values.get(key).map(String::as_str).unwrap_or_else(|| {
+            let keys: Vec<&str> = settings::SETTINGS.iter().map(|setting| setting.key).collect();
+            panic!("No setting named {}, expected one of: {}", key, keys.join(", "))
+        })
+    }
+
+    // Function to get the value of a numeric setting, already checked when the config was read
+    pub fn number(&self, key: &str) -> i32 {
+        self.text(key).parse().unwrap_or_default()
+    }
+
+    // Function to get the value of an on/off setting
+    pub fn is_on(&self, key: &str) -> bool {
+        self.text(key) == "on"
+    }
+}
+
+// Function to read every setting from the config file, warning about the values that were replaced
+// by their default
+pub fn read_config() -> io::Result<Config> {
+    let content = fs::read_to_string(config_file()?)?;
+    let (config, warnings) = parse_config(&content);
+    for warning in warnings {
+        eprintln!("{}", warning);
+    }
+
+    Ok(config)
+}
+
+// Function to build the settings from the content of a config file, starting from the defaults in
+// settings.rs. A value its setting doesn't accept, such as one an older version allowed, falls back
+// to the default with a warning rather than keeping term-typist from starting. Keys that aren't
+// settings are ignored.
+fn parse_config(content: &str) -> (Config, Vec<String>) {
+    let mut values: HashMap<&'static str, String> = settings::SETTINGS
+        .iter()
+        .map(|setting| (setting.key, setting.default.to_string()))
+        .collect();
+    let mut warnings = Vec::new();
+
+    for line in content.lines() {
+        let mut parts = line.split_whitespace();
+        let Some(key) = parts.next() else {
+            continue;
+        };
+        let Some(setting) = settings::find_key(key) else {
+            continue;
+        };
+
+        let value = parts.next().unwrap_or("");
+        if settings::is_valid(setting, value) {
+            values.insert(setting.key, value.to_string());
+        } else {
+            warnings.push(format!(
+                "Invalid value '{}' for {} in config file, using the default {}",
+                value, key, setting.default
+            ));
+            values.insert(setting.key, setting.default.to_string());
+        }
+    }
+
+    (Config { values }, warnings)
+}
+
+// Function to write a key and its value into the config file, replacing any previous value. The
+// config file is created first when it doesn't exist yet, as in a fresh data directory.
+pub fn write_value(key: &str, value: &str) -> io::Result<()> {
+    create_config()?;
     let file_path = config_file()?;
     let mut file_content = String::new();
 
@@ -30,20 +119,20 @@ pub fn write_nb_of_words(nb_cmds: i32) -> io::Result<()> {
     }
 
     let mut updated_content = String::new();
-    let mut nb_cmds_found = false;
+    let mut key_found = false;
 
     for line in file_content.lines() {
-        if line.trim().starts_with("nb_of_words") {
-            nb_cmds_found = true;
-            updated_content.push_str(&format!("nb_of_words {}\n", nb_cmds));
+        if line.split_whitespace().next() == Some(key) {
+            key_found = true;
+            updated_content.push_str(&format!("{} {}\n", key, value));
         } else {
-            updated_content.push_str(&line);
+            updated_content.push_str(line);
             updated_content.push('\n');
         }
     }
 
-    if !nb_cmds_found {
-        updated_content.push_str(&format!("nb_of_words {}\n", nb_cmds));
+    if !key_found {
+        updated_content.push_str(&format!("{} {}\n", key, value));
     }
 
     let mut file = File::create(&file_path)?;
@@ -52,33 +141,20 @@ pub fn write_nb_of_words(nb_cmds: i32) -> io::Result<()> {
     Ok(())
 }
 
-// Function to read numberd of words from config file
-pub fn read_nb_of_words() -> io::Result<i32> {
-    let file_path = config_file()?;
-    let file = File::open(&file_path)?;
-    let reader = BufReader::new(file);
-
-    for line in reader.lines() {
-        let line = line?;
-        if line.trim().starts_with("nb_of_words") {
-            let nb_cmds_str = line.split_whitespace().skip(1).next().ok_or_else(|| {
-                io::Error::new(io::ErrorKind::InvalidData, "Invalid format for nb_of_words")
-            })?;
-            let nb_cmds = nb_cmds_str.parse::<i32>().map_err(|_| {
-                io::Error::new(io::ErrorKind::InvalidData, "Failed to parse nb_of_words")
-            })?;
-            return Ok(nb_cmds);
-        }
+// Function to get the data directory chosen with TERM_TYPIST_HOME or --data-dir, if any
+pub fn data_dir() -> Option<PathBuf> {
+    match env::var_os(DATA_DIR_VAR) {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => None,
     }
-
-    // If nb_of_words variable is not found, return 30
-    Ok(30)
 }
 
-
-
 // Function to get the path of the config file
 fn config_file() -> Result<PathBuf, io::Error> {
+    if let Some(dir) = data_dir() {
+        return Ok(dir.join("term-typist.conf"));
+    }
+
     let config_dir = match dirs::config_dir() {
         Some(path) => path,
         None => return Err(io::Error::new(io::ErrorKind::NotFound, "Config directory not found")),
@@ -103,3 +179,41 @@ fn file_exists(file_path: &PathBuf) -> bool {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config_reads_valid_values() {
+        let (config, warnings) = parse_config("nb_of_words 12\nstrict_space on\nunknown_key 3\n\n");
+
+        assert!(warnings.is_empty());
+        assert_eq!(config.number("nb_of_words"), 12);
+        assert!(config.is_on("strict_space"));
+        assert_eq!(config.text("display"), "full");
+    }
+
+    #[test]
+    fn parse_config_falls_back_to_the_default_of_invalid_values() {
+        let (config, warnings) = parse_config("nb_of_words 0\ndisplay\ncursor_style block\n");
+
+        assert_eq!(config.number("nb_of_words"), 30);
+        assert_eq!(config.text("display"), "full");
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("'0' for nb_of_words"));
+    }
+
+    #[test]
+    fn every_default_is_a_valid_value() {
+        for setting in settings::SETTINGS {
+            assert!(settings::is_valid(setting, setting.default), "{}", setting.key);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "No setting named nb_words")]
+    fn text_panics_on_an_unknown_key() {
+        parse_config("").0.text("nb_words");
+    }
+}
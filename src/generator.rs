@@ -1,17 +1,49 @@
+use std::collections::VecDeque;
 use std::env;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufRead};
 use std::path::PathBuf;
 use rand::seq::SliceRandom; 
+use rand::Rng;
 
+use crate::config;
 
+// Maximum number of words kept from a practice text
+const MAX_PRACTICE_WORDS: usize = 100;
 
-fn read_words() -> io::Result<Vec<String>> {
-    let mut file_path = PathBuf::new();
+pub const GENERATORS: [&str; 4] = ["words", "lorem", "pseudo", "identifiers"];
+
+const LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet consectetur adipiscing elit sed do eiusmod tempor \
+incididunt ut labore et dolore magna aliqua Ut enim ad minim veniam quis nostrud exercitation ullamco \
+laboris nisi ut aliquip ex ea commodo consequat Duis aute irure dolor in reprehenderit in voluptate velit \
+esse cillum dolore eu fugiat nulla pariatur Excepteur sint occaecat cupidatat non proident sunt in culpa \
+qui officia deserunt mollit anim id est laborum";
+
+const ONSETS: [&str; 22] = [
+    "b", "c", "d", "f", "g", "h", "j", "k", "l", "m", "n", "p", "r", "s", "t", "v", "w", "z", "br", "st", "tr", "pl",
+];
+const VOWELS: [&str; 8] = ["a", "e", "i", "o", "u", "ai", "ea", "ou"];
+const CODAS: [&str; 8] = ["", "", "", "n", "r", "s", "l", "m"];
+
+const IDENTIFIER_PARTS: [&str; 40] = [
+    "get", "set", "is", "has", "load", "parse", "find", "create", "update", "delete", "user", "id", "by", "max", "min",
+    "retry", "count", "config", "file", "name", "index", "buffer", "size", "item", "list", "map", "key", "value",
+    "request", "response", "handler", "error", "result", "total", "next", "node", "cache", "token", "path", "len",
+];
+
+
+
+// Function to get the directory holding the word lists, inside the data directory when one is set
+fn words_dir() -> io::Result<PathBuf> {
+    if let Some(data_dir) = config::data_dir() {
+        return Ok(data_dir.join("words"));
+    }
+
+    let mut dir_path = PathBuf::new();
     
     if let Some(home_dir) = env::var_os("HOME") {
-        file_path.push(home_dir);
-        file_path.push(".local/share/term-typist/words/words.txt");
+        dir_path.push(home_dir);
+        dir_path.push(".local/share/term-typist/words");
     } else {
         return Err(io::Error::new(
             io::ErrorKind::NotFound,
@@ -19,6 +51,63 @@ fn read_words() -> io::Result<Vec<String>> {
         ));
     }
 
+    Ok(dir_path)
+}
+
+// Function to get the file name of the word list for a language. english is the words.txt shipped
+// with the project, so "words" isn't a language of its own and an english.txt is never read.
+fn word_list_file(language: &str) -> Option<String> {
+    match language {
+        "english" => Some("words.txt".to_string()),
+        "words" => None,
+        _ => Some(format!("{}.txt", language)),
+    }
+}
+
+// Function to get the path of the word list for a language
+fn words_file(language: &str) -> io::Result<PathBuf> {
+    match word_list_file(language) {
+        Some(file_name) => Ok(words_dir()?.join(file_name)),
+        None => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No word list for language: {}", language),
+        )),
+    }
+}
+
+// Function to list the languages of every word list found in the words directory. A file only
+// counts when word_list_file maps its language back to it, so every list has exactly one name.
+pub fn word_lists() -> io::Result<Vec<String>> {
+    let mut languages = Vec::new();
+
+    for entry in fs::read_dir(words_dir()?)? {
+        let path = entry?.path();
+        let (Some(stem), Some(file_name)) = (
+            path.file_stem().and_then(|stem| stem.to_str()),
+            path.file_name().and_then(|file_name| file_name.to_str()),
+        ) else {
+            continue;
+        };
+        let language = if stem == "words" { "english" } else { stem };
+        if word_list_file(language).as_deref() == Some(file_name) {
+            languages.push(language.to_string());
+        }
+    }
+
+    languages.sort();
+    Ok(languages)
+}
+
+// Function to check whether a word list is installed for a language
+pub fn has_word_list(language: &str) -> bool {
+    match words_file(language) {
+        Ok(file_path) => file_path.is_file(),
+        Err(_) => false,
+    }
+}
+
+fn read_words(language: &str) -> io::Result<Vec<String>> {
+    let file_path = words_file(language)?;
     let file = File::open(&file_path)?;
     let reader = io::BufReader::new(file);
     let mut words = Vec::new();
@@ -33,8 +122,16 @@ fn read_words() -> io::Result<Vec<String>> {
 }
 
 
-pub fn generate_random_sentence(num_words: usize) -> String {
-    let words = match read_words() {
+// Function to generate a sentence of random words. Only words between min_length and max_length
+// characters are used (0 meaning no limit), and no word comes back within repeat_gap words of itself.
+pub fn generate_random_sentence(
+    num_words: usize,
+    language: &str,
+    repeat_gap: usize,
+    min_length: usize,
+    max_length: usize,
+) -> String {
+    let words = match read_words(language) {
         Ok(words) => words,
         Err(err) => {
             eprintln!("Error reading words: {}", err);
@@ -42,15 +139,237 @@ pub fn generate_random_sentence(num_words: usize) -> String {
         }
     };
 
+    let words = usable_words(words, min_length, max_length);
+    if words.is_empty() {
+        if max_length == 0 {
+            eprintln!("No words of at least {} characters in the word list", min_length);
+        } else {
+            eprintln!("No words between {} and {} characters long in the word list", min_length, max_length);
+        }
+        return String::new();
+    }
+
+    pick_words(&words, num_words, repeat_gap, &mut rand::thread_rng()).join(" ")
+}
+
+// Function to keep the distinct words of a word list between min_length and max_length characters (0
+// meaning no limit). Blank lines and duplicates are dropped so the repeat gap in pick_words is capped
+// by the distinct words, otherwise a list with fewer distinct words than the gap would never find a
+// word to pick.
+fn usable_words(words: Vec<String>, min_length: usize, max_length: usize) -> Vec<String> {
+    let mut words: Vec<String> = words
+        .into_iter()
+        .map(|word| word.trim().to_string())
+        .filter(|word| {
+            let length = word.chars().count();
+            length > 0 && length >= min_length && (max_length == 0 || length <= max_length)
+        })
+        .collect();
+    words.sort();
+    words.dedup();
+    words
+}
+
+// Function to pick random words out of a list of distinct words so that no word comes back within
+// repeat_gap words of itself
+fn pick_words<'a, R: Rng>(words: &'a [String], num_words: usize, repeat_gap: usize, rng: &mut R) -> Vec<&'a str> {
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    // A small word list can't always honour the gap, so it is capped below the number of distinct words
+    let repeat_gap = repeat_gap.min(words.len() - 1);
+    let mut recent: VecDeque<&String> = VecDeque::new();
+    let mut picked = Vec::new();
+
+    for _ in 0..num_words {
+        let mut random_word = words.choose(rng).unwrap();
+        while recent.contains(&random_word) {
+            random_word = words.choose(rng).unwrap();
+        }
+
+        recent.push_back(random_word);
+        if recent.len() > repeat_gap {
+            recent.pop_front();
+        }
+
+        picked.push(random_word.as_str());
+    }
+
+    picked
+}
+
+// Function to generate the classic lorem ipsum text, starting over when more words are needed
+pub fn generate_lorem_ipsum(num_words: usize) -> String {
+    LOREM_IPSUM
+        .split_whitespace()
+        .cycle()
+        .take(num_words)
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// Function to generate pronounceable made-up words of one to three syllables
+pub fn generate_pseudo_words(num_words: usize) -> String {
     let mut rng = rand::thread_rng();
     let mut sentence = String::new();
 
     for _ in 0..num_words {
-        let random_word = words.choose(&mut rng).unwrap();
-        sentence.push_str(random_word);
+        for _ in 0..rng.gen_range(1..=3) {
+            sentence.push_str(ONSETS.choose(&mut rng).unwrap());
+            sentence.push_str(VOWELS.choose(&mut rng).unwrap());
+            sentence.push_str(CODAS.choose(&mut rng).unwrap());
+        }
         sentence.push(' ');
     }
 
     sentence.trim().to_string()
 }
 
+// Function to generate programming identifiers of two or three parts, written in camelCase,
+// PascalCase, snake_case or SCREAMING_SNAKE_CASE and sometimes ending with a digit
+pub fn generate_identifiers(num_words: usize) -> String {
+    let mut rng = rand::thread_rng();
+    let mut sentence = String::new();
+
+    for _ in 0..num_words {
+        let parts: Vec<&str> = (0..rng.gen_range(2..=3))
+            .map(|_| *IDENTIFIER_PARTS.choose(&mut rng).unwrap())
+            .collect();
+
+        let identifier = match rng.gen_range(0..4) {
+            0 => parts
+                .iter()
+                .enumerate()
+                .map(|(index, part)| if index == 0 { part.to_string() } else { capitalize(part) })
+                .collect::<String>(),
+            1 => parts.iter().map(|part| capitalize(part)).collect::<String>(),
+            2 => parts.join("_"),
+            _ => parts.join("_").to_uppercase(),
+        };
+
+        sentence.push_str(&identifier);
+        if rng.gen_bool(0.25) {
+            sentence.push_str(&rng.gen_range(0..10).to_string());
+        }
+        sentence.push(' ');
+    }
+
+    sentence.trim().to_string()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Function to turn any text into practice sentences: only letters, digits, underscores and single
+// spaces are kept since those are the keys the test accepts, and the words are split into parts of
+// MAX_PRACTICE_WORDS so a long text can be practiced one part at a time
+pub fn practice_parts(input: &str) -> Vec<String> {
+    let cleaned: String = input
+        .chars()
+        .filter(|&c| !is_apostrophe(c))
+        .map(|c| if is_typeable(c) { c } else { ' ' })
+        .collect();
+    let words: Vec<&str> = cleaned.split_whitespace().collect();
+
+    words.chunks(MAX_PRACTICE_WORDS).map(|part| part.join(" ")).collect()
+}
+
+fn is_typeable(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Apostrophes are dropped rather than turned into spaces so contractions such as "it's" stay one
+// word, while other punctuation becomes a space and keeps "end,start" or "well-known" apart
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn word_list(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn every_word_list_has_one_name() {
+        assert_eq!(word_list_file("english").as_deref(), Some("words.txt"));
+        assert_eq!(word_list_file("german").as_deref(), Some("german.txt"));
+        assert_eq!(word_list_file("words"), None);
+    }
+
+    #[test]
+    fn usable_words_drop_blank_lines_and_duplicates() {
+        let words = usable_words(word_list(&["cat", "", "dog ", "cat", "   ", "dog"]), 0, 0);
+        assert_eq!(words, vec!["cat", "dog"]);
+    }
+
+    #[test]
+    fn usable_words_filter_by_length() {
+        let words = word_list(&["a", "to", "cat", "bird", "horse"]);
+
+        assert_eq!(usable_words(words.clone(), 3, 0), vec!["bird", "cat", "horse"]);
+        assert_eq!(usable_words(words.clone(), 2, 4), vec!["bird", "cat", "to"]);
+        assert!(usable_words(words, 6, 0).is_empty());
+    }
+
+    #[test]
+    fn pick_words_caps_the_gap_by_the_distinct_words() {
+        // Two distinct words behind duplicates and blank lines with a gap of 5 used to loop forever
+        let words = usable_words(word_list(&["cat", "dog", "cat", "", "dog"]), 0, 0);
+        let picked = pick_words(&words, 20, 5, &mut StdRng::seed_from_u64(1));
+
+        assert_eq!(picked.len(), 20);
+        assert!(picked.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn pick_words_keep_the_gap() {
+        let words = word_list(&["a", "b", "c", "d", "e"]);
+        let picked = pick_words(&words, 50, 3, &mut StdRng::seed_from_u64(7));
+
+        for (index, word) in picked.iter().enumerate() {
+            assert!(!picked[index.saturating_sub(3)..index].contains(word));
+        }
+    }
+
+    #[test]
+    fn pick_words_from_a_single_word() {
+        let words = word_list(&["only"]);
+        assert_eq!(pick_words(&words, 3, 2, &mut StdRng::seed_from_u64(0)), vec!["only"; 3]);
+    }
+
+    #[test]
+    fn practice_parts_keep_contractions_as_one_word() {
+        assert_eq!(practice_parts("it's fine, isn\u{2019}t it"), vec!["its fine isnt it"]);
+    }
+
+    #[test]
+    fn practice_parts_split_words_joined_by_punctuation() {
+        assert_eq!(practice_parts("end,start well-known"), vec!["end start well known"]);
+    }
+
+    #[test]
+    fn practice_parts_split_long_texts() {
+        let input = vec!["word"; MAX_PRACTICE_WORDS + 1].join(" ");
+        let parts = practice_parts(&input);
+
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].split(' ').count(), MAX_PRACTICE_WORDS);
+        assert_eq!(parts[1], "word");
+    }
+
+    #[test]
+    fn practice_parts_of_text_without_words() {
+        assert!(practice_parts(" ... \n").is_empty());
+    }
+}
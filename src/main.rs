@@ -1,25 +1,57 @@
 use std::env;
+use std::io::{self, Read};
 
 mod config;
 mod generator;
+mod settings;
 mod ui;
 mod wpm;
 
 fn help() {
     println!("Usage: term-typist [options] | term-typist");
     println!("Options:");   
-    println!("-h               Display this help message");
-    println!("-w <number>      Set the number of words");     
-  
+    println!("-h [filter]      Display this help message, or only the settings matching the filter");
+    println!("--stdin [part]   Practice on text piped into term-typist, long texts being split into parts");
+    println!("-L               List the installed word lists");
+    println!("--data-dir <dir> Keep the config file and word lists in <dir> (same as TERM_TYPIST_HOME)");
+    settings::print_help("");
 }
 
+// Function to read the config once and start a test with it
+fn start_test(practice_text: Option<String>) {
+    let _ = config::create_config();
+    match config::read_config() {
+        Ok(config) => ui::listen_for_alphabets(&config, practice_text),
+        Err(err) => eprintln!("Error reading config: {}", err),
+    }
+}
+
+// Function to read the part number given after --stdin, counted from 1 up to the number of parts
+fn parse_part(part: &str, nb_of_parts: usize) -> Option<usize> {
+    match part.parse::<usize>() {
+        Ok(nb) if nb >= 1 && nb <= nb_of_parts => Some(nb),
+        _ => None,
+    }
+}
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+
+    // The data directory is picked before any other flag so every setting is read from and written to it
+    if let Some(index) = args.iter().position(|arg| arg == "--data-dir") {
+        match args.get(index + 1) {
+            Some(dir) => env::set_var(config::DATA_DIR_VAR, dir),
+            None => {
+                eprintln!("Directory not provided for the --data-dir flag.");
+                help();
+                return;
+            }
+        }
+        args.drain(index..index + 2);
+    }
 
     if args.len() == 1 {
-        let _ = config::create_config();
-        let _ = ui::listen_for_alphabets();
+        start_test(None);
         return;
     }
 
@@ -28,32 +60,93 @@ fn main() {
     while let Some(arg) = iter.next() {
         match arg.as_str() {
             "-h" => {
-                help();
+                match iter.next() {
+                    Some(filter) => settings::print_help(filter),
+                    None => help(),
+                }
                 return;
             }
-            "-w" => {
-                if let Some(nb_of_words) = iter.next() {
-                    match nb_of_words.parse::<i32>() {
-                        Ok(nb) => {
-                            let _ = config::write_nb_of_words(nb);
+            "--stdin" => {
+                let mut input = String::new();
+                if let Err(err) = io::stdin().read_to_string(&mut input) {
+                    eprintln!("Error reading stdin: {}", err);
+                    return;
+                }
+
+                let parts = generator::practice_parts(&input);
+                if parts.is_empty() {
+                    eprintln!("No text to practice found on stdin.");
+                    return;
+                }
+
+                // Long texts are split into parts, the first one being practiced unless another is asked for
+                let part = match iter.next() {
+                    Some(part) => match parse_part(part, parts.len()) {
+                        Some(nb) => nb,
+                        None => {
+                            eprintln!("Invalid part provided for --stdin flag: {} (the text has {} parts)", part, parts.len());
+                            return;
                         }
-                        Err(_) => {
-                            eprintln!("Invalid value provided for -s flag: {}", nb_of_words);
+                    },
+                    None => 1,
+                };
+                if parts.len() > 1 {
+                    eprintln!("Practicing part {} of {}, pass the part number after --stdin to pick another.", part, parts.len());
+                }
+
+                start_test(Some(parts[part - 1].clone()));
+                return;
+            }
+            "-L" => {
+                let active = match config::read_config() {
+                    Ok(config) => config.text("language").to_string(),
+                    Err(_) => "english".to_string(),
+                };
+                match generator::word_lists() {
+                    Ok(languages) => {
+                        for language in languages {
+                            let marker = if language == active { "*" } else { " " };
+                            println!("{} {}", marker, language);
+                        }
+                    }
+                    Err(err) => eprintln!("Error listing word lists: {}", err),
+                }
+                return;
+            }
+            _ => match settings::find(arg) {
+                Some(setting) => {
+                    if let Some(value) = iter.next() {
+                        if let Err(err) = settings::apply(setting, value) {
+                            eprintln!("{}", err);
                             help();
                             return;
                         }
+                    } else {
+                        eprintln!("Value not provided for the {} flag, expected {}.", setting.flag, setting.argument);
+                        help();
+                        return;
                     }
-                } else {
-                    eprintln!("Unit value not provided for the -s flag.");
+                }
+                None => {
+                    eprintln!("Invalid argument: {}", arg);
                     help();
                     return;
                 }
-            }
-            _ => {
-                eprintln!("Invalid argument: {}", arg);
-                help();
-                return;
-            }
+            },
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_part_accepts_only_existing_parts() {
+        assert_eq!(parse_part("1", 2), Some(1));
+        assert_eq!(parse_part("2", 2), Some(2));
+        assert_eq!(parse_part("0", 2), None);
+        assert_eq!(parse_part("3", 2), None);
+        assert_eq!(parse_part("two", 2), None);
+    }
+}
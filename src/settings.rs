@@ -0,0 +1,359 @@
+use std::env;
+use std::path::Path;
+
+use crate::config;
+use crate::generator;
+use crate::ui;
+
+// Kind of value a setting accepts, checked before it is written to the config file
+pub enum Value {
+    // A whole number no lower than the given minimum
+    Number(i32),
+    // A whole number between the given minimum and maximum
+    Range(i32, i32),
+    // One word out of a fixed list
+    Choice(&'static [&'static str]),
+    // The name of an installed word list
+    WordList,
+    // A file path without spaces, or off
+    File,
+}
+
+pub struct Setting {
+    pub flag: &'static str,
+    pub key: &'static str,
+    pub default: &'static str,
+    pub argument: &'static str,
+    pub description: &'static str,
+    pub section: &'static str,
+    pub value: Value,
+}
+
+pub const SECTIONS: [&str; 3] = ["Test", "Display", "Theme"];
+
+// Every option that can be set from the command line and saved in the config file. Adding a setting
+// only takes an entry here: the config is read and checked against this table as a whole, and the
+// code using the setting gets it from the Config by its key.
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        flag: "-w",
+        key: "nb_of_words",
+        default: "30",
+        argument: "<number>",
+        description: "Set the number of words",
+        section: "Test",
+        value: Value::Number(1),
+    },
+    Setting {
+        flag: "-g",
+        key: "generator",
+        default: "words",
+        argument: "<generator>",
+        description: "Set what to type",
+        section: "Test",
+        value: Value::Choice(&generator::GENERATORS),
+    },
+    Setting {
+        flag: "-l",
+        key: "language",
+        default: "english",
+        argument: "<language>",
+        description: "Set the language of the words (words/<language>.txt)",
+        section: "Test",
+        value: Value::WordList,
+    },
+    Setting {
+        flag: "-n",
+        key: "repeat_gap",
+        default: "0",
+        argument: "<number>",
+        description: "Keep a word from repeating within <number> words",
+        section: "Test",
+        value: Value::Number(0),
+    },
+    Setting {
+        flag: "-m",
+        key: "min_word_length",
+        default: "0",
+        argument: "<length>",
+        description: "Set the minimum length of words (0 for none)",
+        section: "Test",
+        value: Value::Number(0),
+    },
+    Setting {
+        flag: "-M",
+        key: "max_word_length",
+        default: "0",
+        argument: "<length>",
+        description: "Set the maximum length of words (0 for none)",
+        section: "Test",
+        value: Value::Number(0),
+    },
+    Setting {
+        flag: "-S",
+        key: "strict_space",
+        default: "off",
+        argument: "<mode>",
+        description: "Refuse other keys where a space is expected",
+        section: "Test",
+        value: Value::Choice(&["on", "off"]),
+    },
+    Setting {
+        flag: "-a",
+        key: "afk_timeout",
+        default: "5",
+        argument: "<seconds>",
+        description: "Set the pause after which you count as AFK",
+        section: "Test",
+        value: Value::Number(1),
+    },
+    Setting {
+        flag: "-b",
+        key: "bot_wpm",
+        default: "0",
+        argument: "<wpm>",
+        description: "Race a bot typing the same text at <wpm> (0 for none)",
+        section: "Test",
+        value: Value::Number(0),
+    },
+    Setting {
+        flag: "-A",
+        key: "min_accuracy",
+        default: "0",
+        argument: "<percent>",
+        description: "Set the accuracy to stay above during a test, 0 for none",
+        section: "Test",
+        value: Value::Range(0, 100),
+    },
+    Setting {
+        flag: "-G",
+        key: "accuracy_gate",
+        default: "warn",
+        argument: "<action>",
+        description: "Set what happens below the minimum accuracy",
+        section: "Test",
+        value: Value::Choice(&ui::ACCURACY_GATES),
+    },
+    Setting {
+        flag: "-d",
+        key: "display",
+        default: "full",
+        argument: "<mode>",
+        description: "Show the full text or a few words at a time",
+        section: "Display",
+        value: Value::Choice(&ui::DISPLAY_MODES),
+    },
+    Setting {
+        flag: "-c",
+        key: "cursor_style",
+        default: "block",
+        argument: "<style>",
+        description: "Set the cursor style",
+        section: "Display",
+        value: Value::Choice(&ui::CURSOR_STYLES),
+    },
+    Setting {
+        flag: "-k",
+        key: "lookahead",
+        default: "0",
+        argument: "<number>",
+        description: "Hide the text past the next <number> characters (0 to show all)",
+        section: "Display",
+        value: Value::Number(0),
+    },
+    Setting {
+        flag: "-r",
+        key: "wpm_window",
+        default: "5",
+        argument: "<seconds>",
+        description: "Set the window of the live WPM",
+        section: "Display",
+        value: Value::Number(1),
+    },
+    Setting {
+        flag: "-o",
+        key: "overlay_file",
+        default: "off",
+        argument: "<file>",
+        description: "Write live stats to <file> every second for stream overlays (off to disable)",
+        section: "Display",
+        value: Value::File,
+    },
+    Setting {
+        flag: "-s",
+        key: "color_scheme",
+        default: "default",
+        argument: "<scheme>",
+        description: "Set the color scheme",
+        section: "Theme",
+        value: Value::Choice(&ui::COLOR_SCHEMES),
+    },
+    Setting {
+        flag: "-e",
+        key: "incorrect_modifier",
+        default: "none",
+        argument: "<style>",
+        description: "Add a style to incorrect characters",
+        section: "Theme",
+        value: Value::Choice(&ui::INCORRECT_MODIFIERS),
+    },
+    Setting {
+        flag: "-p",
+        key: "color_palette",
+        default: "auto",
+        argument: "<palette>",
+        description: "Set the colors the terminal supports",
+        section: "Theme",
+        value: Value::Choice(&ui::COLOR_PALETTES),
+    },
+];
+
+// Function to find the setting behind a command line flag
+pub fn find(flag: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|setting| setting.flag == flag)
+}
+
+// Function to find the setting stored under a config key
+pub fn find_key(key: &str) -> Option<&'static Setting> {
+    SETTINGS.iter().find(|setting| setting.key == key)
+}
+
+// Function to check a value against what the setting accepts. Word lists are only checked for being
+// installed when the setting is changed, so removing one doesn't make the whole config unreadable.
+pub fn is_valid(setting: &Setting, value: &str) -> bool {
+    match setting.value {
+        Value::Number(min) => value.parse::<i32>().is_ok_and(|nb| nb >= min),
+        Value::Range(min, max) => value.parse::<i32>().is_ok_and(|nb| (min..=max).contains(&nb)),
+        Value::Choice(choices) => choices.contains(&value),
+        // The name becomes part of a path inside the words directory, so it can't lead out of it
+        Value::WordList => !value.is_empty() && !value.contains(['/', '\\']) && !value.contains(".."),
+        // The config file separates keys and values with whitespace, so a path can't contain any
+        Value::File => !value.is_empty() && !value.contains(char::is_whitespace),
+    }
+}
+
+// Function to check a value against what the setting accepts and write it into the config file
+pub fn apply(setting: &Setting, value: &str) -> Result<(), String> {
+    if !is_valid(setting, value) {
+        return Err(format!("Invalid value provided for {} flag: {}", setting.flag, value));
+    }
+    if matches!(setting.value, Value::WordList) && !generator::has_word_list(value) {
+        return Err(format!("No word list found for language: {}", value));
+    }
+
+    let value = match env::current_dir() {
+        Ok(current_dir) => saved_value(setting, value, &current_dir),
+        Err(_) => value.to_string(),
+    };
+    config::write_value(setting.key, &value).map_err(|err| format!("Error writing {}: {}", setting.key, err))
+}
+
+// Function to get the value written into the config file. A relative path is saved from the current
+// directory so it still points to the same file later.
+fn saved_value(setting: &Setting, value: &str, current_dir: &Path) -> String {
+    if matches!(setting.value, Value::File) && value != "off" && Path::new(value).is_relative() {
+        current_dir.join(value).to_string_lossy().to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// Function to print the settings of every section matching a filter, listing the accepted words of
+// each choice. An empty filter prints them all.
+pub fn print_help(filter: &str) {
+    let mut found = false;
+
+    for section in SECTIONS {
+        let matching: Vec<&Setting> = SETTINGS
+            .iter()
+            .filter(|setting| setting.section == section && matches_filter(setting, filter))
+            .collect();
+        if matching.is_empty() {
+            continue;
+        }
+        found = true;
+
+        println!();
+        println!("{}:", section);
+
+        for setting in matching {
+            let usage = format!("{} {}", setting.flag, setting.argument);
+            match setting.value {
+                Value::Choice(choices) => {
+                    println!("{:<16} {} ({})", usage, setting.description, choices.join(", "))
+                }
+                Value::Range(min, max) => println!("{:<16} {} ({}-{})", usage, setting.description, min, max),
+                _ => println!("{:<16} {}", usage, setting.description),
+            }
+        }
+    }
+
+    if !found {
+        println!("No settings match: {}", filter);
+    }
+}
+
+// Function to fuzzy match a filter against the flag, key and description of a setting: every
+// character of the filter has to appear in the label in the same order, ignoring case
+fn matches_filter(setting: &Setting, filter: &str) -> bool {
+    let label = format!("{} {} {}", setting.flag, setting.key, setting.description).to_lowercase();
+    let mut label_chars = label.chars();
+
+    filter
+        .to_lowercase()
+        .chars()
+        .all(|c| label_chars.any(|label_char| label_char == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_matches_characters_in_order() {
+        let words = find("-w").unwrap();
+
+        assert!(matches_filter(words, "wrd"));
+        assert!(matches_filter(words, "NB_OF"));
+        assert!(matches_filter(words, ""));
+        assert!(!matches_filter(words, "sdrow"));
+    }
+
+    #[test]
+    fn is_valid_checks_the_kind_of_value() {
+        assert!(is_valid(find("-w").unwrap(), "1"));
+        assert!(!is_valid(find("-w").unwrap(), "0"));
+        assert!(!is_valid(find("-w").unwrap(), "ten"));
+        assert!(is_valid(find("-A").unwrap(), "0"));
+        assert!(is_valid(find("-A").unwrap(), "100"));
+        assert!(!is_valid(find("-A").unwrap(), "150"));
+        assert!(!is_valid(find("-A").unwrap(), "-1"));
+        assert!(is_valid(find("-d").unwrap(), "chunk"));
+        assert!(!is_valid(find("-d").unwrap(), "half"));
+        assert!(is_valid(find("-o").unwrap(), "/tmp/stats.txt"));
+        assert!(!is_valid(find("-o").unwrap(), "/tmp/my stats.txt"));
+        assert!(!is_valid(find("-o").unwrap(), ""));
+    }
+
+    #[test]
+    fn language_names_stay_inside_the_words_directory() {
+        let language = find("-l").unwrap();
+
+        assert!(is_valid(language, "german"));
+        assert!(!is_valid(language, "../../etc/foo"));
+        assert!(!is_valid(language, "lists/german"));
+        assert!(!is_valid(language, "lists\\german"));
+        assert!(!is_valid(language, ".."));
+    }
+
+    #[test]
+    fn relative_paths_are_saved_from_the_current_directory() {
+        let overlay = find("-o").unwrap();
+        let current_dir = Path::new("/home/user");
+
+        assert_eq!(saved_value(overlay, "stats.txt", current_dir), "/home/user/stats.txt");
+        assert_eq!(saved_value(overlay, "/tmp/stats.txt", current_dir), "/tmp/stats.txt");
+        assert_eq!(saved_value(overlay, "off", current_dir), "off");
+        assert_eq!(saved_value(find("-l").unwrap(), "german", current_dir), "german");
+    }
+}
@@ -1,63 +1,259 @@
+use std::env;
+use std::fs;
 use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::thread;
 use std::time::{Instant, Duration};
 use termion::input::TermRead;
 use termion::raw::IntoRawMode;
 use termion::cursor::DetectCursorPos;
+use rand::Rng;
 
-use crate::generator::generate_random_sentence;
-use crate::config::read_nb_of_words;
+use crate::generator::{generate_identifiers, generate_lorem_ipsum, generate_pseudo_words, generate_random_sentence};
+use crate::config::Config;
+use crate::wpm;
 
 const GREEN: &str = "\x1b[32m";
 const RED: &str = "\x1b[31m";
 const WHITE: &str = "\x1b[0m";
+const YELLOW: &str = "\x1b[33m";
+const BLUE: &str = "\x1b[34m";
+const ORANGE: &str = "\x1b[38;5;208m";
+const STRIKE: &str = "\x1b[9m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const REVERSE: &str = "\x1b[7m";
+const UNDERLINE: &str = "\x1b[4m";
+const BLINK_REVERSE: &str = "\x1b[5;7m";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const BAR_CURSOR: &str = "\x1b[6 q";
+const DEFAULT_CURSOR: &str = "\x1b[0 q";
 
-pub fn listen_for_alphabets() {
-    let nb_of_words = match read_nb_of_words() {
-        Ok(num) => num,
-        Err(err) => {
-            eprintln!("Error reading number of words: {}", err);
-            return;
-        }
+pub const CURSOR_STYLES: [&str; 4] = ["block", "underline", "bar", "blink"];
+pub const DISPLAY_MODES: [&str; 2] = ["full", "chunk"];
+pub const COLOR_SCHEMES: [&str; 3] = ["default", "colorblind", "mono"];
+pub const COLOR_PALETTES: [&str; 3] = ["auto", "16", "256"];
+pub const INCORRECT_MODIFIERS: [&str; 3] = ["none", "underline", "bold"];
+pub const ACCURACY_GATES: [&str; 2] = ["warn", "abort"];
+
+// Number of words shown at once in chunk display mode
+const CHUNK_WORDS: usize = 5;
+// Number of cells in the words progress bar
+const PROGRESS_WIDTH: usize = 20;
+// Number of keypresses before the minimum accuracy is checked, so a first typo doesn't trip it
+const MIN_ACCURACY_KEYPRESSES: usize = 20;
+
+pub fn listen_for_alphabets(config: &Config, practice_text: Option<String>) {
+    let nb_of_words = config.number("nb_of_words") as usize;
+    let wpm_window = config.number("wpm_window") as usize;
+    let afk_timeout = config.number("afk_timeout") as f64;
+    let language = config.text("language");
+    let strict_space = config.is_on("strict_space");
+    let bot_wpm = config.number("bot_wpm") as usize;
+    let overlay_file = match config.text("overlay_file") {
+        "off" => None,
+        path => Some(path.to_string()),
+    };
+    let min_accuracy = config.number("min_accuracy") as f64;
+    let accuracy_gate = config.text("accuracy_gate");
+    let lookahead = config.number("lookahead") as usize;
+    let generator = config.text("generator");
+    let repeat_gap = config.number("repeat_gap") as usize;
+    let min_word_length = config.number("min_word_length") as usize;
+    let max_word_length = config.number("max_word_length") as usize;
+    let color_palette = config.text("color_palette");
+    let extended_colors = match color_palette {
+        "16" => false,
+        "256" => true,
+        _ => supports_256_colors(),
+    };
+    let (correct_color, incorrect_color) =
+        scheme_colors(config.text("color_scheme"), config.text("incorrect_modifier"), extended_colors);
+    let initial_text = match practice_text {
+        Some(text) => text,
+        None => match generator {
+            "lorem" => generate_lorem_ipsum(nb_of_words),
+            "pseudo" => generate_pseudo_words(nb_of_words),
+            "identifiers" => generate_identifiers(nb_of_words),
+            _ => generate_random_sentence(
+                nb_of_words,
+                language,
+                repeat_gap,
+                min_word_length,
+                max_word_length,
+            ),
+        },
     };
-    let initial_text = generate_random_sentence(nb_of_words as usize);
-    let stdin = io::stdin();
+    // The generator has already reported why there is nothing to type
+    if initial_text.is_empty() {
+        return;
+    }
+    // Word lists may contain multi-byte characters, so positions are counted in chars
+    let text_len = initial_text.chars().count();
+    let display = config.text("display").to_string();
+    let width = terminal_width();
+    let text_style = TextStyle {
+        correct_color,
+        incorrect_color,
+        cursor_style: config.text("cursor_style").to_string(),
+        rows: text_rows(&initial_text, &display, width),
+        display,
+        lookahead,
+        width,
+    };
+    let total_words = initial_text.split_whitespace().count();
+    // Keys are read from the terminal itself since stdin may be the pipe the practice text came from
+    let tty = termion::get_tty().expect("Failed to open the terminal");
     let mut stdout = io::stdout().into_raw_mode().expect("Failed to set raw mode");
 
     let mut i = 0;
     let mut char_count = 0;
-    let mut char_status: Vec<char> = vec!['N'; initial_text.len()];
-    let mut colored_text = String::new();
+    let mut error_count = 0;
+    let mut corrected_count = 0;
+    let mut accuracy_aborted = false;
+    let mut key_gaps: Vec<f64> = Vec::new();
+    let mut char_status: Vec<char> = vec!['N'; text_len];
+    let mut typed_chars: Vec<char> = vec![' '; text_len];
+    let mut char_times: Vec<Option<f64>> = vec![None; text_len];
 
+    // The bar style uses the terminal cursor itself, the others highlight the character instead
+    if text_style.cursor_style == "bar" {
+        print!("{}", BAR_CURSOR);
+    } else {
+        print!("{}", HIDE_CURSOR);
+    }
+    // Make room for every row the text wraps onto, the status line below it and the bot's progress
+    // when racing one, scrolling if the text starts too close to the bottom of the terminal
+    let reserved_rows = text_style.rows + if bot_wpm > 0 { 1 } else { 0 };
+    print!("{}\x1b[{}A\r", "\n".repeat(reserved_rows as usize), reserved_rows);
     stdout.flush().unwrap();
-    println!("{}", initial_text);
-
-    let (x, _) = stdout.cursor_pos().unwrap(); // Get the current cursor position
+    let (_, text_row) = stdout.cursor_pos().unwrap();
+    let status_row = text_row + text_style.rows;
+    let bot_row = status_row + 1;
+    draw_text(&initial_text, 0, &char_status, &text_style, text_row);
 
     let start_time = Instant::now();
+    let mut last_key_time = start_time;
+    let running = Arc::new(AtomicBool::new(true));
+    let timer_running = Arc::clone(&running);
+    let keypresses = Arc::new(AtomicUsize::new(0));
+    let timer_keypresses = Arc::clone(&keypresses);
+    let live_wpm = Arc::new(AtomicUsize::new(0));
+    let timer_live_wpm = Arc::clone(&live_wpm);
+    let words_done = Arc::new(AtomicUsize::new(0));
+    let timer_words_done = Arc::clone(&words_done);
+    let errors = Arc::new(AtomicUsize::new(0));
+    let timer_errors = Arc::clone(&errors);
+    let bot_chars = Arc::new(AtomicUsize::new(0));
+    let timer_bot_chars = Arc::clone(&bot_chars);
+    let timer_overlay_file = overlay_file.clone();
 
+    // The duration thread also samples the raw WPM of every second for the consistency score
     let duration_handle = thread::spawn(move || {
-        loop {
+        let mut samples = Vec::new();
+        let mut last_keypresses = 0;
+        // Seconds without a keypress are held back until the pause ends, then dropped if it lasted long
+        // enough to count as AFK, the same way AFK time is left out of the WPM
+        let mut idle_seconds = 0;
+        let mut rng = rand::thread_rng();
+        let mut bot_typed = 0.0;
+
+        while timer_running.load(Ordering::Relaxed) {
             let elapsed = start_time.elapsed();
             let seconds = elapsed.as_secs();
-            print!("\x1b[{};0H\x1b[KTime elapsed: {} seconds", x + 1, seconds); // Clear line and move cursor to second line
-            io::stdout().flush().unwrap();
+            let mut live_samples = samples.clone();
+            live_samples.extend(vec![0.0; idle_seconds]);
+            timer_live_wpm.store(wpm::rolling_wpm(&live_samples, wpm_window).round() as usize, Ordering::Relaxed);
+            draw_status(
+                status_row,
+                seconds,
+                timer_live_wpm.load(Ordering::Relaxed),
+                timer_words_done.load(Ordering::Relaxed),
+                total_words,
+                timer_errors.load(Ordering::Relaxed),
+                &status_extra(accuracy_warning(
+                    min_accuracy,
+                    timer_keypresses.load(Ordering::Relaxed),
+                    timer_errors.load(Ordering::Relaxed),
+                )),
+            );
+            if let Some(progress) = bot_progress(bot_wpm, &timer_bot_chars, text_len) {
+                draw_bot(bot_row, progress);
+            }
+            if let Some(path) = &timer_overlay_file {
+                write_overlay(
+                    path,
+                    timer_live_wpm.load(Ordering::Relaxed),
+                    wpm::accuracy(timer_keypresses.load(Ordering::Relaxed), timer_errors.load(Ordering::Relaxed)),
+                    timer_words_done.load(Ordering::Relaxed),
+                    total_words,
+                );
+            }
             thread::sleep(Duration::from_secs(1));
+
+            if !timer_running.load(Ordering::Relaxed) {
+                break;
+            }
+            if bot_wpm > 0 {
+                // The bot's speed varies by up to 15% every second so it doesn't move like a clock
+                let speed = bot_wpm as f64 * rng.gen_range(0.85..1.15);
+                bot_typed += speed * 5.0 / 60.0;
+                timer_bot_chars.store((bot_typed as usize).min(text_len), Ordering::Relaxed);
+            }
+            let current_keypresses = timer_keypresses.load(Ordering::Relaxed);
+            if current_keypresses == last_keypresses {
+                idle_seconds += 1;
+                continue;
+            }
+            if (idle_seconds as f64) < afk_timeout {
+                samples.extend(vec![0.0; idle_seconds]);
+            }
+            idle_seconds = 0;
+            samples.push(wpm::words_per_minute(current_keypresses - last_keypresses, 1.0));
+            last_keypresses = current_keypresses;
         }
+
+        if (idle_seconds as f64) < afk_timeout {
+            samples.extend(vec![0.0; idle_seconds]);
+        }
+        samples
     });
     
-    for key in stdin.keys() {
+    for key in tty.keys() {
         match key {
             Ok(key_event) => {
+                key_gaps.push(last_key_time.elapsed().as_secs_f64());
+                last_key_time = Instant::now();
+
                 match key_event {
                     termion::event::Key::Backspace => {
                         if i > 0 {
                             i -= 1;
+                            if char_status[i] == 'F' {
+                                corrected_count += 1;
+                            }
                             char_status[i] = 'N';
-                            
+                            char_times[i] = None;
                         }
 
                     }
+                    // Option+Backspace on macOS (Alt+Backspace elsewhere) and Ctrl+W delete back to the start of the word
+                    termion::event::Key::Alt('\x7f') | termion::event::Key::Ctrl('w') => {
+                        while i > 0 {
+                            i -= 1;
+                            if char_status[i] == 'F' {
+                                corrected_count += 1;
+                            }
+                            char_status[i] = 'N';
+                            char_times[i] = None;
+
+                            if i == 0 || initial_text.chars().nth(i - 1) == Some(' ') {
+                                break;
+                            }
+                        }
+                    }
                     termion::event::Key::Char(c) => {
                         if c == '.'{
                             break;
@@ -67,36 +263,61 @@ pub fn listen_for_alphabets() {
                                 char_status[i] = 'T';
                             } else {
                                 char_status[i] = 'F';
+                                error_count += 1;
                             }
+                            typed_chars[i] = c;
+                            char_times[i] = Some(start_time.elapsed().as_secs_f64());
                             i += 1;
                             char_count += 1;
                         }
-                        if c.is_alphabetic() {
-                            if c == initial_text.chars().nth(i).unwrap() {
-                                char_status[i] = 'T';
+                        // Digits and underscores are needed for the identifiers drill
+                        if c.is_alphanumeric() || c == '_' {
+                            let expected = initial_text.chars().nth(i).unwrap();
+                            // With strict spaces a wrong key where a space is expected counts as an
+                            // error but doesn't move on, so the rest of the text stays aligned
+                            if strict_space && expected == ' ' {
+                                error_count += 1;
+                                char_count += 1;
                             } else {
-                                char_status[i] = 'F';
+                                if c == expected {
+                                    char_status[i] = 'T';
+                                } else {
+                                    char_status[i] = 'F';
+                                    error_count += 1;
+                                }
+                                typed_chars[i] = c;
+                                char_times[i] = Some(start_time.elapsed().as_secs_f64());
+                                char_count += 1;
+                                i += 1;
                             }
-                            char_count += 1;
-                            i += 1;
                         }
                     }
                     _ => {}
                 }
+                keypresses.store(char_count, Ordering::Relaxed);
 
-                colored_text.clear();
-                for (index, char) in initial_text.chars().enumerate() {
-                    match char_status[index] {
-                        'N' => colored_text.push_str(WHITE),
-                        'T' => colored_text.push_str(GREEN),
-                        'F' => colored_text.push_str(RED),
-                        _ => {}
-                    }
-                    colored_text.push(char);
+                // A word counts as done once the space after it, or the last character of the text, is typed
+                let mut completed_words = initial_text.chars().take(i).filter(|&c| c == ' ').count();
+                if i == text_len {
+                    completed_words += 1;
+                }
+                words_done.store(completed_words, Ordering::Relaxed);
+                errors.store(error_count, Ordering::Relaxed);
+                if accuracy_gate == "abort" && accuracy_warning(min_accuracy, char_count, error_count).is_some() {
+                    accuracy_aborted = true;
+                    break;
                 }
-                colored_text.push_str(WHITE);
-                print!("\r{}", colored_text); 
-                io::stdout().flush().unwrap();      
+                draw_status(
+                    status_row,
+                    start_time.elapsed().as_secs(),
+                    live_wpm.load(Ordering::Relaxed),
+                    completed_words,
+                    total_words,
+                    error_count,
+                    &status_extra(accuracy_warning(min_accuracy, char_count, error_count)),
+                );
+
+                draw_text(&initial_text, i, &char_status, &text_style, text_row);
             }
             Err(err) => {
                 eprintln!("Error reading input: {}", err);
@@ -104,12 +325,421 @@ pub fn listen_for_alphabets() {
             }
         }
         // Break the loop when the sentence is completed
-        if i == initial_text.len() {
+        if i == text_len {
             break;
         }
         stdout.flush().expect("Failed to flush stdout");
     }
 
-    // Wait for the duration thread to finish
-    duration_handle.join().unwrap();
+    // Pauses long enough to count as AFK are left out of the WPM
+    let afk_seconds = wpm::afk_seconds(&key_gaps, afk_timeout);
+    let elapsed_seconds = start_time.elapsed().as_secs_f64() - afk_seconds;
+
+    // Stop the duration thread and wait for it to finish
+    running.store(false, Ordering::Relaxed);
+    let samples = duration_handle.join().unwrap();
+
+    // Restore the terminal cursor and leave raw mode before printing the result below the status line
+    print!("{}{}\x1b[{};0H", DEFAULT_CURSOR, SHOW_CURSOR, text_row + reserved_rows);
+    drop(stdout);
+    println!();
+    println!(
+        "{}",
+        typed_diff(&initial_text, &char_status, &typed_chars, &text_style.correct_color, &text_style.incorrect_color)
+    );
+
+    if accuracy_aborted {
+        println!("Test aborted: accuracy fell below {:.0}%", min_accuracy);
+    }
+    // Stats of an interrupted test only cover the part that was typed
+    if i < text_len {
+        println!(
+            "Test interrupted: typed {} of {} characters in {:.0} seconds",
+            i, text_len, elapsed_seconds
+        );
+    }
+
+    let correct_chars = char_status.iter().filter(|&&status| status == 'T').count();
+    let uncorrected_count = char_status.iter().filter(|&&status| status == 'F').count();
+    // The overlay is left showing the final result rather than the last live tick
+    if let Some(path) = &overlay_file {
+        write_overlay(
+            path,
+            wpm::words_per_minute(correct_chars, elapsed_seconds).round() as usize,
+            wpm::accuracy(char_count, error_count),
+            words_done.load(Ordering::Relaxed),
+            total_words,
+        );
+    }
+    println!("WPM: {:.0}", wpm::words_per_minute(correct_chars, elapsed_seconds));
+    println!("Accuracy: {:.1}%", wpm::accuracy(char_count, error_count));
+    println!("Correctness: {:.1}%", wpm::correctness(&char_status));
+    println!("Consistency: {:.0}%", wpm::consistency(&samples));
+    println!("Burst WPM: {:.0}", wpm::burst_wpm(&samples, wpm_window));
+    println!("Errors: {} corrected, {} uncorrected", corrected_count, uncorrected_count);
+    if afk_seconds > 0.0 {
+        println!("AFK: {:.0} seconds excluded from WPM", afk_seconds);
+    }
+    if bot_wpm > 0 && i == text_len {
+        if bot_chars.load(Ordering::Relaxed) < text_len {
+            println!("Race: you finished ahead of the {} WPM bot", bot_wpm);
+        } else {
+            println!("Race: the {} WPM bot finished first", bot_wpm);
+        }
+    }
+
+    let mut word_speeds = wpm::word_speeds(&initial_text, &char_times);
+    if !word_speeds.is_empty() {
+        word_speeds.sort_by(|a, b| a.wpm.total_cmp(&b.wpm));
+        println!("Slowest words:");
+        for speed in word_speeds.iter().take(5) {
+            println!("  {:<16} {:>5.2}s {:>4.0} WPM", speed.word, speed.seconds, speed.wpm);
+        }
+    }
+}
+
+// How the text is drawn, fixed for the whole test. `rows` is the number of terminal rows the text
+// wraps onto at `width` columns.
+struct TextStyle {
+    correct_color: String,
+    incorrect_color: String,
+    cursor_style: String,
+    display: String,
+    lookahead: usize,
+    width: usize,
+    rows: u16,
+}
+
+// Function to draw the visible part of the text over the rows starting at `row`: typed characters in
+// the color of their status, the current one highlighted by the cursor style and characters past the
+// lookahead masked. It is used for the first frame as well as after every key so the two can't differ.
+fn draw_text(text: &str, i: usize, char_status: &[char], style: &TextStyle, row: u16) {
+    let (start, end) = visible_range(text, i, &style.display);
+    let mut colored_text = String::new();
+    let mut word_has_error = false;
+
+    for (index, char) in text.chars().enumerate().take(end).skip(start) {
+        if char_status[index] == 'F' {
+            word_has_error = true;
+        }
+        match char_status[index] {
+            'N' => colored_text.push_str(WHITE),
+            // The space after a completed word is underlined in the color telling whether the word had an error
+            _ if char == ' ' => {
+                if word_has_error {
+                    colored_text.push_str(&style.incorrect_color);
+                } else {
+                    colored_text.push_str(&style.correct_color);
+                }
+                colored_text.push_str(UNDERLINE);
+            }
+            'T' => colored_text.push_str(&style.correct_color),
+            'F' => colored_text.push_str(&style.incorrect_color),
+            _ => {}
+        }
+        if char == ' ' {
+            word_has_error = false;
+        }
+        let shown_char = masked_char(char, index, i, style.lookahead);
+        if index == i {
+            colored_text.push_str(cursor_highlight(&style.cursor_style));
+            colored_text.push(shown_char);
+            colored_text.push_str(WHITE);
+        } else {
+            colored_text.push(shown_char);
+        }
+    }
+    colored_text.push_str(WHITE);
+    // Every row of the text is cleared first since a chunk can be shorter than the one before it
+    for offset in 0..style.rows {
+        print!("\x1b[{};0H\x1b[K", row + offset);
+    }
+    print!("\x1b[{};0H{}", row, colored_text);
+    if style.cursor_style == "bar" {
+        // Move the terminal cursor back onto the current character, which may be on a wrapped row
+        let offset = i - start;
+        print!("\x1b[{};{}H", row + (offset / style.width) as u16, offset % style.width + 1);
+    }
+    io::stdout().flush().unwrap();
+}
+
+// Function to draw the status line below the text: elapsed time, live WPM, the errors made, a
+// progress bar of the completed words and any extra such as an accuracy warning. The cursor is saved
+// and restored around it so typing isn't disturbed, and the line is clipped to the terminal width
+// since a wrapped status line would scroll the screen at the bottom of the terminal.
+fn draw_status(
+    row: u16,
+    seconds: u64,
+    live_wpm: usize,
+    words_done: usize,
+    total_words: usize,
+    errors: usize,
+    extra: &str,
+) {
+    let progress = if total_words == 0 { 0.0 } else { words_done as f64 / total_words as f64 };
+    let line = format!(
+        "Time {}s  WPM {}  Err {}  {}/{} {}{}",
+        seconds,
+        live_wpm,
+        errors,
+        words_done,
+        total_words,
+        progress_bar(progress),
+        extra
+    );
+    let width = terminal_width();
+
+    print!("\x1b7\x1b[{};0H\x1b[K{}{}\x1b8", row, clip_to_width(&line, width), WHITE);
+    io::stdout().flush().unwrap();
+}
+
+// Function to get the width of the terminal, assuming 80 columns when it can't be found
+fn terminal_width() -> usize {
+    match termion::terminal_size() {
+        Ok((columns, _)) if columns > 0 => columns as usize,
+        _ => 80,
+    }
+}
+
+// Function to cut a line down to the given number of visible characters, leaving escape codes out
+// of the count
+fn clip_to_width(line: &str, width: usize) -> String {
+    let mut clipped = String::new();
+    let mut visible = 0;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Keep the whole escape code, which ends with its first letter
+            clipped.push(c);
+            for code_char in chars.by_ref() {
+                clipped.push(code_char);
+                if code_char.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+            continue;
+        }
+        if visible == width {
+            break;
+        }
+        clipped.push(c);
+        visible += 1;
+    }
+
+    clipped
+}
+
+// Function to write the live stats to the overlay file for streaming software to read. The stats go
+// to a temporary file in the same directory first, then replace the overlay file in one rename, so a
+// reader never catches it empty or half-written. Failing to write it is ignored since nothing can be
+// reported in the middle of the test.
+fn write_overlay(path: &str, live_wpm: usize, accuracy: f64, words_done: usize, total_words: usize) {
+    let stats = format!("WPM: {}  Accuracy: {:.1}%  Words: {}/{}\n", live_wpm, accuracy, words_done, total_words);
+    let temp_path = format!("{}.tmp", path);
+    if fs::write(&temp_path, stats).is_ok() {
+        let _ = fs::rename(&temp_path, path);
+    }
+}
+
+// Function to render a progress between 0 and 1 as a bar followed by its percentage
+fn progress_bar(progress: f64) -> String {
+    let filled = ((progress * PROGRESS_WIDTH as f64).round() as usize).min(PROGRESS_WIDTH);
+    format!("[{}{}] {:.0}%", "█".repeat(filled), "░".repeat(PROGRESS_WIDTH - filled), progress * 100.0)
+}
+
+// Function to build the end of the status line, a warning when accuracy is below the minimum. It is
+// kept short so the status line still fits in 80 columns with it.
+fn status_extra(accuracy_warning: Option<f64>) -> String {
+    match accuracy_warning {
+        Some(min_accuracy) => format!("  {}Accuracy < {:.0}%{}", RED, min_accuracy, WHITE),
+        None => String::new(),
+    }
+}
+
+// Function to draw the bot's progress on its own row below the status line, which leaves the
+// status line room for everything else
+fn draw_bot(row: u16, progress: f64) {
+    let line = format!("Bot {}", progress_bar(progress));
+    print!("\x1b7\x1b[{};0H\x1b[K{}\x1b8", row, clip_to_width(&line, terminal_width()));
+    io::stdout().flush().unwrap();
+}
+
+// Function to check the accuracy so far against the minimum, returning the minimum when it is
+// missed. A minimum of 0 turns the check off.
+fn accuracy_warning(min_accuracy: f64, keypresses: usize, errors: usize) -> Option<f64> {
+    if min_accuracy <= 0.0 || keypresses < MIN_ACCURACY_KEYPRESSES {
+        return None;
+    }
+
+    if wpm::accuracy(keypresses, errors) < min_accuracy {
+        Some(min_accuracy)
+    } else {
+        None
+    }
+}
+
+// Function to get how far through the text the bot is, if a bot is racing
+fn bot_progress(bot_wpm: usize, bot_chars: &AtomicUsize, text_len: usize) -> Option<f64> {
+    if bot_wpm == 0 {
+        return None;
+    }
+
+    Some(bot_chars.load(Ordering::Relaxed) as f64 / text_len as f64)
+}
+
+// Function to guess whether the terminal can show the 256 color palette
+fn supports_256_colors() -> bool {
+    if env::var_os("COLORTERM").is_some() {
+        return true;
+    }
+
+    match env::var("TERM") {
+        Ok(term) => term.contains("256color") || term.contains("direct"),
+        Err(_) => false,
+    }
+}
+
+// Function to get the escape codes for correct and incorrect characters in a color scheme. Both
+// start with a reset so a modifier on one character doesn't carry over to the next.
+fn scheme_colors(scheme: &str, incorrect_modifier: &str, extended_colors: bool) -> (String, String) {
+    // Orange only exists in the 256 color palette, yellow is the closest of the basic 16
+    let orange = if extended_colors { ORANGE } else { YELLOW };
+    let (correct, incorrect) = match scheme {
+        // Blue and orange stay apart for red-green color blindness, the underline doesn't rely on color at all
+        "colorblind" => (BLUE, format!("{}{}", orange, UNDERLINE)),
+        // Reverse video is left to the cursor, so a mistyped character can't pass for a second cursor
+        "mono" => (BOLD, format!("{}{}", DIM, STRIKE)),
+        _ => (GREEN, RED.to_string()),
+    };
+    let modifier = match incorrect_modifier {
+        "underline" => UNDERLINE,
+        "bold" => BOLD,
+        _ => "",
+    };
+
+    (format!("{}{}", WHITE, correct), format!("{}{}{}", WHITE, incorrect, modifier))
+}
+
+// Function to get the range of characters to display: the whole text, or in chunk mode the
+// current word followed by the next few words
+fn visible_range(text: &str, i: usize, display: &str) -> (usize, usize) {
+    let chars: Vec<char> = text.chars().collect();
+    if display != "chunk" {
+        return (0, chars.len());
+    }
+
+    let start = chars[..i].iter().rposition(|&c| c == ' ').map_or(0, |space| space + 1);
+    let mut end = start;
+    let mut words = 0;
+
+    while end < chars.len() {
+        if chars[end] == ' ' {
+            words += 1;
+            if words == CHUNK_WORDS {
+                break;
+            }
+        }
+        end += 1;
+    }
+
+    (start, end)
+}
+
+// Function to count the terminal rows the text needs at a width: the whole text, or in chunk mode
+// the longest chunk shown at any point of the test
+fn text_rows(text: &str, display: &str, width: usize) -> u16 {
+    let word_starts = text.chars().enumerate().filter(|&(_, c)| c == ' ').map(|(index, _)| index + 1);
+    let longest = std::iter::once(0)
+        .chain(word_starts)
+        .map(|i| {
+            let (start, end) = visible_range(text, i, display);
+            end - start
+        })
+        .max()
+        .unwrap_or(0);
+
+    longest.div_ceil(width).max(1) as u16
+}
+
+// Function to hide a character as a dot in memory mode when it lies `lookahead` or more characters
+// past the current one. Spaces stay visible so the words keep their shape; 0 turns masking off.
+fn masked_char(char: char, index: usize, i: usize, lookahead: usize) -> char {
+    if lookahead > 0 && index >= i + lookahead && char != ' ' {
+        '·'
+    } else {
+        char
+    }
+}
+
+// Function to get the escape code highlighting the current character for a cursor style
+fn cursor_highlight(cursor_style: &str) -> &'static str {
+    match cursor_style {
+        "underline" => UNDERLINE,
+        "blink" => BLINK_REVERSE,
+        "bar" => "",
+        _ => REVERSE,
+    }
+}
+
+// Function to render the text with every mistyped character struck through,
+// followed by the character that was actually pressed
+fn typed_diff(
+    text: &str,
+    char_status: &[char],
+    typed_chars: &[char],
+    correct_color: &str,
+    incorrect_color: &str,
+) -> String {
+    let mut diff = String::new();
+
+    for (index, char) in text.chars().enumerate() {
+        match char_status[index] {
+            'T' => {
+                diff.push_str(correct_color);
+                diff.push(char);
+            }
+            'F' => {
+                diff.push_str(incorrect_color);
+                diff.push_str(STRIKE);
+                diff.push(char);
+                diff.push_str(WHITE);
+                diff.push_str(YELLOW);
+                // Show a typed space as an underscore so it stays visible
+                match typed_chars[index] {
+                    ' ' => diff.push('_'),
+                    typed => diff.push(typed),
+                }
+            }
+            _ => {
+                diff.push_str(WHITE);
+                diff.push(char);
+            }
+        }
+        diff.push_str(WHITE);
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masked_char_hides_from_lookahead_characters_past_the_current_one() {
+        // With the cursor on 2 and a lookahead of 3, characters 2 to 4 stay readable
+        assert_eq!(masked_char('a', 4, 2, 3), 'a');
+        assert_eq!(masked_char('a', 5, 2, 3), '·');
+        assert_eq!(masked_char(' ', 5, 2, 3), ' ');
+        assert_eq!(masked_char('a', 50, 2, 0), 'a');
+    }
+
+    #[test]
+    fn clip_to_width_counts_only_visible_characters() {
+        assert_eq!(clip_to_width("WPM 42  Err 3", 6), "WPM 42");
+        assert_eq!(clip_to_width("short", 80), "short");
+        assert_eq!(clip_to_width("\x1b[31mAccuracy\x1b[0m", 3), "\x1b[31mAcc");
+        assert_eq!(clip_to_width("[██░░] 50%", 4), "[██░");
+    }
 }
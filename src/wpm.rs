@@ -12,3 +12,200 @@ pub fn elapsed_seconds_since_start(start_time: SystemTime) -> f64 {
     elapsed_time.as_secs_f64()
 }
 
+
+// Function to calculate words per minute, counting five characters as one word
+pub fn words_per_minute(nb_of_chars: usize, elapsed_seconds: f64) -> f64 {
+    if elapsed_seconds <= 0.0 {
+        return 0.0;
+    }
+
+    (nb_of_chars as f64 / 5.0) / (elapsed_seconds / 60.0)
+}
+
+// Function to calculate accuracy over every keypress, so errors fixed with backspace still count
+pub fn accuracy(nb_of_keypresses: usize, nb_of_errors: usize) -> f64 {
+    if nb_of_keypresses == 0 {
+        return 0.0;
+    }
+
+    (nb_of_keypresses.saturating_sub(nb_of_errors)) as f64 / nb_of_keypresses as f64 * 100.0
+}
+
+// Function to calculate correctness of the final text, ignoring errors fixed along the way
+pub fn correctness(char_status: &[char]) -> f64 {
+    let correct = char_status.iter().filter(|&&status| status == 'T').count();
+    let typed = char_status.iter().filter(|&&status| status != 'N').count();
+
+    if typed == 0 {
+        return 0.0;
+    }
+
+    correct as f64 / typed as f64 * 100.0
+}
+
+// Function to calculate consistency as one minus the coefficient of variation of the samples
+pub fn consistency(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    if mean <= 0.0 {
+        return 0.0;
+    }
+
+    let variance = samples.iter().map(|sample| (sample - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+    let coefficient_of_variation = variance.sqrt() / mean;
+
+    ((1.0 - coefficient_of_variation) * 100.0).max(0.0)
+}
+
+// Function to calculate the average WPM of the last `window` per-second samples
+pub fn rolling_wpm(samples: &[f64], window: usize) -> f64 {
+    if samples.is_empty() || window == 0 {
+        return 0.0;
+    }
+
+    let recent = &samples[samples.len().saturating_sub(window)..];
+    recent.iter().sum::<f64>() / recent.len() as f64
+}
+
+// Function to find the highest rolling WPM reached over any `window` consecutive samples
+pub fn burst_wpm(samples: &[f64], window: usize) -> f64 {
+    if window == 0 || samples.len() < window {
+        return rolling_wpm(samples, window);
+    }
+
+    samples
+        .windows(window)
+        .map(|recent| recent.iter().sum::<f64>() / window as f64)
+        .fold(0.0, f64::max)
+}
+
+// Function to add up the pauses between keypresses that lasted long enough to count as AFK
+pub fn afk_seconds(key_gaps: &[f64], afk_timeout: f64) -> f64 {
+    key_gaps.iter().filter(|&&gap| gap >= afk_timeout).sum()
+}
+
+pub struct WordSpeed {
+    pub word: String,
+    pub seconds: f64,
+    pub wpm: f64,
+}
+
+// Function to calculate the time taken and WPM of every fully typed word. Each word is timed from
+// the keystroke before it, so the space leading into it counts; the first word is timed from its
+// own first keystroke.
+pub fn word_speeds(text: &str, char_times: &[Option<f64>]) -> Vec<WordSpeed> {
+    let mut speeds = Vec::new();
+    let mut start = 0;
+
+    for word in text.split(' ') {
+        let end = start + word.chars().count();
+        let previous = if start > 0 { start - 1 } else { start };
+        let typed_chars = end - previous - 1;
+
+        let times = char_times.get(previous..end);
+        if let Some(times) = times.filter(|times| times.iter().all(Option::is_some)) {
+            let seconds = times[times.len() - 1].unwrap() - times[0].unwrap();
+            if typed_chars > 0 && seconds > 0.0 {
+                speeds.push(WordSpeed {
+                    word: word.to_string(),
+                    seconds,
+                    wpm: words_per_minute(typed_chars, seconds),
+                });
+            }
+        }
+
+        start = end + 1;
+    }
+
+    speeds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_per_minute_counts_five_characters_as_a_word() {
+        assert_eq!(words_per_minute(50, 60.0), 10.0);
+        assert_eq!(words_per_minute(50, 0.0), 0.0);
+    }
+
+    #[test]
+    fn accuracy_counts_every_error() {
+        assert_eq!(accuracy(0, 0), 0.0);
+        assert_eq!(accuracy(10, 1), 90.0);
+        assert_eq!(accuracy(2, 5), 0.0);
+    }
+
+    #[test]
+    fn correctness_ignores_untyped_characters() {
+        assert_eq!(correctness(&['T', 'F', 'T', 'T', 'N', 'N']), 75.0);
+        assert_eq!(correctness(&['N', 'N']), 0.0);
+    }
+
+    #[test]
+    fn consistency_of_steady_and_uneven_samples() {
+        assert_eq!(consistency(&[60.0, 60.0, 60.0]), 100.0);
+        assert_eq!(consistency(&[50.0, 150.0]), 50.0);
+        assert_eq!(consistency(&[0.0, 300.0]), 0.0);
+        assert_eq!(consistency(&[0.0, 0.0]), 0.0);
+        assert_eq!(consistency(&[]), 0.0);
+    }
+
+    #[test]
+    fn rolling_wpm_uses_the_last_samples() {
+        assert_eq!(rolling_wpm(&[10.0, 20.0, 30.0], 2), 25.0);
+        assert_eq!(rolling_wpm(&[10.0, 20.0, 30.0], 10), 20.0);
+        assert_eq!(rolling_wpm(&[], 5), 0.0);
+    }
+
+    #[test]
+    fn burst_wpm_finds_the_fastest_window() {
+        assert_eq!(burst_wpm(&[10.0, 50.0, 60.0, 20.0], 2), 55.0);
+        // A window longer than the test falls back to the average of every sample
+        assert_eq!(burst_wpm(&[10.0, 50.0], 5), 30.0);
+        assert_eq!(burst_wpm(&[], 5), 0.0);
+    }
+
+    #[test]
+    fn afk_seconds_only_counts_long_pauses() {
+        assert_eq!(afk_seconds(&[0.2, 6.0, 1.0, 5.0], 5.0), 11.0);
+    }
+
+    #[test]
+    fn word_speeds_time_each_word_from_the_space_before_it() {
+        let times = [Some(0.0), Some(0.5), Some(1.0), Some(2.0), Some(2.5), Some(3.0), Some(3.5)];
+        let speeds = word_speeds("ab cd ef", &times[..6]);
+
+        // The first word is timed from its own first key, "cd" from the space before it
+        assert_eq!(speeds.len(), 2);
+        assert_eq!(speeds[0].word, "ab");
+        assert_eq!(speeds[0].seconds, 0.5);
+        assert_eq!(speeds[0].wpm, words_per_minute(1, 0.5));
+        assert_eq!(speeds[1].word, "cd");
+        assert_eq!(speeds[1].seconds, 1.5);
+        assert_eq!(speeds[1].wpm, words_per_minute(2, 1.5));
+    }
+
+    #[test]
+    fn word_speeds_skip_a_one_letter_first_word() {
+        let times = [Some(0.0), Some(0.5), Some(1.0), Some(1.5)];
+        let speeds = word_speeds("a bc", &times);
+
+        assert_eq!(speeds.len(), 1);
+        assert_eq!(speeds[0].word, "bc");
+    }
+
+    #[test]
+    fn word_speeds_skip_words_with_untimed_characters() {
+        // The "d" of "cd" was backspaced away, so the word isn't fully typed
+        let times = [Some(0.0), Some(0.5), Some(1.0), Some(1.5), None];
+        let speeds = word_speeds("ab cd", &times);
+
+        assert_eq!(speeds.len(), 1);
+        assert_eq!(speeds[0].word, "ab");
+    }
+}